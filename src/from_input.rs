@@ -0,0 +1,65 @@
+use std::str::FromStr;
+
+use crate::InputString;
+
+/// A type that can be parsed from a whole line of whitespace-separated fields.
+///
+/// Implemented for tuples of arity 1 through 12 where every element implements [FromStr], so a
+/// line can be destructured straight into a typed tuple via [InputString::parse_tuple] or
+/// [parse_line](crate::BadInput::parse_line).
+///
+/// # Examples
+/// ```
+/// use bad_input::{FromInput, InputString};
+///
+/// let input: InputString = "3 foo 1.5".into();
+/// let (a, b, c): (u32, String, f64) = input.parse_tuple();
+/// assert_eq!(a, 3);
+/// assert_eq!(b, "foo");
+/// assert_eq!(c, 1.5);
+/// ```
+pub trait FromInput: Sized {
+    /// Parses `s` into `Self`.
+    ///
+    /// # Panics
+    ///
+    /// - If the number of whitespace-separated fields in `s` does not match the arity of `Self` or
+    /// - If any field fails to parse.
+    fn from_input(s: &InputString) -> Self;
+}
+
+macro_rules! impl_from_input {
+    ($n:literal; $($t:ident),+) => {
+        impl<$($t: FromStr),+> FromInput for ($($t,)+) {
+            fn from_input(s: &InputString) -> Self {
+                let parts: Vec<&str> = s.as_str().split_ascii_whitespace().collect();
+                if parts.len() != $n {
+                    match s.span() {
+                        Some(span) => panic!(
+                            "Expected {} fields, found {} {}",
+                            $n,
+                            parts.len(),
+                            span.render()
+                        ),
+                        None => panic!("Expected {} fields, found {}", $n, parts.len()),
+                    }
+                }
+                let mut parts = parts.into_iter();
+                ($(s.substr(parts.next().unwrap()).parse::<$t>(),)+)
+            }
+        }
+    };
+}
+
+impl_from_input!(1; A);
+impl_from_input!(2; A, B);
+impl_from_input!(3; A, B, C);
+impl_from_input!(4; A, B, C, D);
+impl_from_input!(5; A, B, C, D, E);
+impl_from_input!(6; A, B, C, D, E, F);
+impl_from_input!(7; A, B, C, D, E, F, G);
+impl_from_input!(8; A, B, C, D, E, F, G, H);
+impl_from_input!(9; A, B, C, D, E, F, G, H, I);
+impl_from_input!(10; A, B, C, D, E, F, G, H, I, J);
+impl_from_input!(11; A, B, C, D, E, F, G, H, I, J, K);
+impl_from_input!(12; A, B, C, D, E, F, G, H, I, J, K, L);
@@ -1,17 +1,46 @@
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::{FromInput, Span};
+
+#[derive(Debug)]
 pub struct InputString {
     inner: String,
+    span: Option<Span>,
+}
+
+impl PartialEq for InputString {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
 }
 
+impl Eq for InputString {}
+
 impl InputString {
     pub fn new() -> Self {
         Self {
             inner: String::new(),
+            span: None,
         }
     }
 
+    /// Builds an `InputString` tagged with a source [Span], as used by [BadInput](crate::BadInput)
+    /// to track where a line or token came from in the original input.
+    pub fn with_span(inner: String, span: Span) -> Self {
+        Self {
+            inner,
+            span: Some(span),
+        }
+    }
+
+    /// Returns the source [Span] of this string, if it has one.
+    ///
+    /// Strings built through [BadInput](crate::BadInput) carry a span back to the original input;
+    /// strings built from a plain `&str`/`String` (e.g. via `.into()`) do not.
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
     pub fn len(&self) -> usize {
         self.inner.len()
     }
@@ -19,7 +48,15 @@ impl InputString {
     pub fn parse<F: FromStr>(&self) -> F {
         use std::any::type_name;
         let Ok(f) = self.inner.parse::<F>() else {
-            panic!("Could not parse \"{}\" to {}", self.inner, type_name::<F>());
+            match &self.span {
+                Some(span) => panic!(
+                    "Could not parse \"{}\" to {} {}",
+                    self.inner,
+                    type_name::<F>(),
+                    span.render()
+                ),
+                None => panic!("Could not parse \"{}\" to {}", self.inner, type_name::<F>()),
+            }
         };
         f
     }
@@ -28,18 +65,192 @@ impl InputString {
         self.inner.parse::<F>().ok()
     }
 
+    /// Splits the string on ASCII whitespace and parses each field into `T`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let input: InputString = "3 foo 1.5".into();
+    /// let (a, b, c): (u32, String, f64) = input.parse_tuple();
+    /// assert_eq!(a, 3);
+    /// assert_eq!(b, "foo");
+    /// assert_eq!(c, 1.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - If the number of whitespace-separated fields does not match the arity of `T` or
+    /// - If any field fails to parse.
+    pub fn parse_tuple<T: FromInput>(&self) -> T {
+        T::from_input(self)
+    }
+
+    /// Builds the `InputString` for `part`, a substring of `self.inner`, propagating `self`'s span
+    /// (if any) by computing `part`'s byte offset within `self.inner`.
+    ///
+    /// `part` must be a genuine subslice of `self.inner` (as returned by e.g. `str::split_once` on
+    /// `self.as_str()`) — the offset is derived from pointer arithmetic, which is only meaningful
+    /// for a subslice of the same buffer.
+    pub(crate) fn substr(&self, part: &str) -> Self {
+        debug_assert!(
+            self.inner.as_ptr() as usize <= part.as_ptr() as usize
+                && part.as_ptr() as usize + part.len()
+                    <= self.inner.as_ptr() as usize + self.inner.len(),
+            "substr: `part` is not a subslice of `self.inner`"
+        );
+        let offset = part.as_ptr() as usize - self.inner.as_ptr() as usize;
+        Self {
+            inner: part.to_owned(),
+            span: self.span.as_ref().map(|s| s.sub(offset, part.len())),
+        }
+    }
+
     pub fn split<'a>(&'a self, p: &'a str) -> impl Iterator<Item = Self> + 'a {
-        self.inner.split(p).map(|s| s.into())
+        self.inner.split(p).map(|s| self.substr(s))
     }
 
-    /*
-    fn split_with(
+    /// Splits the string by repeatedly applying `splitter` to whatever remains, yielding each
+    /// returned part in turn. `splitter` takes the current remainder and returns the next part
+    /// together with the new remainder, or `None` to stop, in which case the remainder itself (if
+    /// non-empty) is yielded as the final item.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let input: InputString = "1,2,3,done".into();
+    /// let parts: Vec<_> = input
+    ///     .split_with(|s| s.split_once(',').filter(|_| !s.starts_with("done")))
+    ///     .collect();
+    /// assert_eq!(parts, ["1", "2", "3", "done"]);
+    /// ```
+    pub fn split_with<'a>(
         &'a self,
-        mut splitter: impl FnMut(&str) -> Option<(&str, &str)>,
+        splitter: impl FnMut(&str) -> Option<(&str, &str)> + 'a,
     ) -> impl Iterator<Item = Self> + 'a {
-        let mut rest = self.as_str();
-        std::iter::repeat_with(move || {})
-    } */
+        self.split_with_raw(splitter)
+    }
+
+    /// The concretely-typed iterator behind [split_with](InputString::split_with), kept around so
+    /// that `destruct_n_with` can reach its inherent [into_remainder](SplitWith::into_remainder)
+    /// method.
+    fn split_with_raw<F: FnMut(&str) -> Option<(&str, &str)>>(
+        &self,
+        splitter: F,
+    ) -> SplitWith<'_, F> {
+        SplitWith {
+            source: self,
+            rest: Some(self.as_str()),
+            splitter,
+        }
+    }
+
+    /// Consumes and returns the longest prefix of `self` for which every char satisfies `pred`,
+    /// removing it from `self`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let mut input: InputString = "123abc".into();
+    /// assert_eq!(input.take_while(|c| c.is_ascii_digit()), "123");
+    /// assert_eq!(input, "abc");
+    /// ```
+    pub fn take_while(&mut self, pred: impl Fn(char) -> bool) -> Self {
+        let n = self
+            .inner
+            .char_indices()
+            .find(|&(_, c)| !pred(c))
+            .map(|(i, _)| i)
+            .unwrap_or(self.inner.len());
+        self.consume_front(n)
+    }
+
+    /// Consumes and returns everything up to (but not including) the first occurrence of `pat`,
+    /// leaving `pat` at the front of `self`. Consumes all of `self` if `pat` does not occur.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let mut input: InputString = "key=value".into();
+    /// assert_eq!(input.take_until("="), "key");
+    /// assert_eq!(input, "=value");
+    /// ```
+    pub fn take_until(&mut self, pat: &str) -> Self {
+        let n = self.inner.find(pat).unwrap_or(self.inner.len());
+        self.consume_front(n)
+    }
+
+    /// Consumes `literal` from the front of `self`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let mut input: InputString = "(42)".into();
+    /// input.expect("(");
+    /// assert_eq!(input, "42)");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `self` does not start with `literal`.
+    pub fn expect(&mut self, literal: &str) {
+        if !self.inner.starts_with(literal) {
+            match &self.span {
+                Some(span) => panic!(
+                    "Expected \"{}\", found \"{}\" {}",
+                    literal,
+                    self.inner,
+                    span.render()
+                ),
+                None => panic!("Expected \"{}\", found \"{}\"", literal, self.inner),
+            }
+        }
+        self.consume_front(literal.len());
+    }
+
+    /// Consumes any ASCII whitespace from the front of `self`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let mut input: InputString = "   foo".into();
+    /// input.skip_whitespace();
+    /// assert_eq!(input, "foo");
+    /// ```
+    pub fn skip_whitespace(&mut self) {
+        self.take_while(|c| c.is_ascii_whitespace());
+    }
+
+    /// Returns the first character of `self`, without consuming it.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let input: InputString = "abc".into();
+    /// assert_eq!(input.peek_char(), Some('a'));
+    /// ```
+    pub fn peek_char(&self) -> Option<char> {
+        self.inner.chars().next()
+    }
+
+    /// Removes and returns the first `n` bytes of `self`, shrinking `self`'s span (if any) to
+    /// start just after the consumed prefix.
+    fn consume_front(&mut self, n: usize) -> Self {
+        let rest = self.inner.split_off(n);
+        let consumed_inner = std::mem::replace(&mut self.inner, rest);
+        let consumed_span = self.span.as_ref().map(|s| s.sub(0, n));
+        self.span = self.span.as_ref().map(|s| s.sub(n, s.len() - n));
+        Self {
+            inner: consumed_inner,
+            span: consumed_span,
+        }
+    }
 
     /// Returns an array of `N` [InputString]s, with the result from `N` times splitting the input
     /// by `p`.
@@ -58,13 +269,28 @@ impl InputString {
         self.destruct_n([p])
     }
 
+    /// Fallible version of [split_n](Self::split_n): returns `None` instead of panicking if `p`
+    /// does not occur often enough to fill all `N` parts.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::InputString;
+    ///
+    /// let input = InputString::from("key=value");
+    /// let [key, value] = input.try_split_n("=").unwrap();
+    /// assert_eq!(key, "key");
+    /// assert_eq!(value, "value");
+    ///
+    /// let input = InputString::from("noequalsign");
+    /// assert_eq!(input.try_split_n::<2>("="), None);
+    /// ```
     pub fn try_split_n<const N: usize>(&self, p: &str) -> Option<[InputString; N]> {
         self.try_destruct_n([p])
     }
 
     pub fn split_at(&self, n: usize) -> (InputString, InputString) {
         let (lhs, rhs) = self.inner.split_at(n);
-        (lhs.to_string().into(), rhs.to_string().into())
+        (self.substr(lhs), self.substr(rhs))
     }
 
     pub fn chars<'a>(&'a self) -> impl Iterator<Item = char> + 'a {
@@ -120,38 +346,35 @@ impl InputString {
         })
     }
 
+    /// Splits `self` into `M` parts via the shared [SplitWith] primitive: `splitter` is applied
+    /// `M - 1` times to produce the leading parts, and whatever remains after that is taken
+    /// verbatim as the final part.
     fn destruct_n_with<const M: usize>(
         &self,
         mut splitter: impl FnMut(&str) -> (&str, &str),
     ) -> [Self; M] {
-        let mut res = Vec::new();
-        let mut rest = self.as_str();
-        loop {
-            if res.len() == (M - 1) {
-                res.push(rest.into());
-                break res.try_into().unwrap();
-            }
-            let (part, next) = splitter(rest);
-            res.push(part.into());
-            rest = next
-        }
+        let mut iter = self.split_with_raw(move |s| Some(splitter(s)));
+        let mut res: Vec<Self> = (&mut iter).take(M - 1).collect();
+        res.push(iter.into_remainder());
+        res.try_into().unwrap_or_else(|_| unreachable!())
     }
 
+    /// Unlike [destruct_n_with](Self::destruct_n_with), this can't reuse [SplitWith]: `splitter`
+    /// returning `None` before the `M`th part must fail the whole destructure, whereas `SplitWith`
+    /// treats a `None` as "stop and take the remainder" (the behavior `split_with` wants).
     fn try_destruct_n_with<const M: usize>(
         &self,
         mut splitter: impl FnMut(&str) -> Option<(&str, &str)>,
     ) -> Option<[Self; M]> {
-        let mut res = Vec::new();
+        let mut res = Vec::with_capacity(M);
         let mut rest = self.as_str();
-        loop {
-            if res.len() == (M - 1) {
-                res.push(rest.into());
-                break Some(res.try_into().unwrap());
-            }
+        for _ in 0..(M - 1) {
             let (part, next) = splitter(rest)?;
-            res.push(part.into());
-            rest = next
+            res.push(self.substr(part));
+            rest = next;
         }
+        res.push(self.substr(rest));
+        res.try_into().ok()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -167,7 +390,18 @@ impl InputString {
     }
 
     pub fn trim(&self) -> Self {
-        self.inner.trim().into()
+        self.substr(self.inner.trim())
+    }
+
+    /// Drops the last `n` bytes, shrinking the span (if any) to match. Used by
+    /// [BadInput](crate::BadInput) to strip the `\r` left over from a `\r\n` line ending.
+    pub(crate) fn without_trailing(mut self, n: usize) -> Self {
+        let new_len = self.inner.len() - n;
+        self.inner.truncate(new_len);
+        if let Some(span) = &mut self.span {
+            span.truncate(n);
+        }
+        self
     }
 }
 
@@ -206,7 +440,7 @@ impl Into<String> for InputString {
 
 impl From<String> for InputString {
     fn from(inner: String) -> Self {
-        Self { inner }
+        Self { inner, span: None }
     }
 }
 
@@ -214,6 +448,7 @@ impl From<&str> for InputString {
     fn from(inner: &str) -> Self {
         Self {
             inner: inner.to_owned(),
+            span: None,
         }
     }
 }
@@ -247,3 +482,38 @@ impl std::fmt::Display for InputString {
         self.inner.fmt(f)
     }
 }
+
+struct SplitWith<'a, F> {
+    source: &'a InputString,
+    rest: Option<&'a str>,
+    splitter: F,
+}
+
+impl<'a, F: FnMut(&str) -> Option<(&str, &str)>> Iterator for SplitWith<'a, F> {
+    type Item = InputString;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
+        match (self.splitter)(rest) {
+            Some((part, next_rest)) => {
+                self.rest = Some(next_rest);
+                Some(self.source.substr(part))
+            }
+            None => {
+                self.rest = None;
+                if rest.is_empty() {
+                    None
+                } else {
+                    Some(self.source.substr(rest))
+                }
+            }
+        }
+    }
+}
+
+impl<'a, F> SplitWith<'a, F> {
+    /// Consumes the iterator, returning whatever of `source` is left unsplit.
+    fn into_remainder(self) -> InputString {
+        self.source.substr(self.rest.unwrap_or(""))
+    }
+}
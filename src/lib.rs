@@ -16,14 +16,21 @@
 //! assert_eq!(n1.parse::<u64>() + n2.parse::<u64>() + n3.parse::<u64>(), 101);
 //! ```
 
-use std::{io::Read, string::FromUtf8Error};
+use std::{io::Read, str::FromStr, string::FromUtf8Error};
 
+pub use from_input::FromInput;
 pub use input_string::InputString;
+pub use span::Span;
 
 pub struct BadInput<R: Read> {
     reader: R,
     read_buf: [u8; 1024],
     buf: Vec<u8>,
+    pos: usize,
+    line_no: usize,
+    line_start: usize,
+    line_buf: Vec<u8>,
+    delim: u8,
 }
 
 impl<R: Read> BadInput<R> {
@@ -48,10 +55,31 @@ impl<R: Read> BadInput<R> {
     /// assert_eq!(input.line(), "Good bye!");
     /// ```
     pub fn new(reader: R) -> Self {
+        Self::with_delimiter(reader, b'\n')
+    }
+
+    /// Creates a new BadInput from any reader, using `delim` instead of `\n` as the line
+    /// delimiter for [line](BadInput::line), [try_line](BadInput::try_line), and friends.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::with_delimiter("a;b;c".as_bytes(), b';');
+    /// assert_eq!(input.line(), "a");
+    /// assert_eq!(input.line(), "b");
+    /// assert_eq!(input.line(), "c");
+    /// ```
+    pub fn with_delimiter(reader: R, delim: u8) -> Self {
         Self {
             reader,
             read_buf: [0; 1024],
             buf: Vec::new(),
+            pos: 0,
+            line_no: 1,
+            line_start: 0,
+            line_buf: Vec::new(),
+            delim,
         }
     }
 
@@ -122,63 +150,387 @@ impl<R: Read> BadInput<R> {
     ///
     /// If the line contains invalid UTF-8
     pub fn try_line(&mut self) -> Option<InputString> {
-        self.try_read_to_byte(b'\n')
-            .or_else(|| self.empty_buffer().map(|r| r.map_err(|e| e.into())))
+        let delim = self.delim;
+        self.try_read_to_byte(delim)
+            .or_else(|| self.empty_buffer().map(|r| r.map_err(Into::into)))
             .and_then(|e| e.ok())
             .map(|line| {
-                let Some(line) = line.strip_suffix('\n') else {
-                    return line.into();
-                };
-                let Some(line) = line.strip_suffix('\r') else {
-                    return line.into();
-                };
-                line.into()
+                // The `\n` itself is already dropped by the reader; only a `\r` before it remains.
+                if delim != b'\n' || !line.as_str().ends_with('\r') {
+                    return line;
+                }
+                line.without_trailing(1)
             })
     }
 
-    fn empty_buffer(&mut self) -> Option<Result<String, FromUtf8Error>> {
-        if self.buf.is_empty() {
+    /// Reads a line of raw bytes from the input, without requiring it to be valid UTF-8.
+    ///
+    /// Unlike [line](BadInput::line), this never panics on invalid UTF-8 — the bytes are handed
+    /// back as-is, trailing `\r\n`/`\n` stripped.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new(b"Hello\n\xFF\xFE\n".as_slice());
+    /// assert_eq!(input.byte_line(), b"Hello");
+    /// assert_eq!(input.byte_line(), &[0xFF, 0xFE]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there are no more lines to be read.
+    pub fn byte_line(&mut self) -> Vec<u8> {
+        self.try_byte_line().unwrap()
+    }
+
+    /// Reads a line from the input, lossily decoding invalid UTF-8 as the replacement character
+    /// instead of panicking.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new(b"Hello\n\xFF\xFE\n".as_slice());
+    /// assert_eq!(input.line_lossy(), "Hello");
+    /// assert_eq!(input.line_lossy(), "\u{FFFD}\u{FFFD}");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there are no more lines to be read.
+    pub fn line_lossy(&mut self) -> String {
+        String::from_utf8_lossy(&self.try_byte_line().unwrap()).into_owned()
+    }
+
+    fn try_byte_line(&mut self) -> Option<Vec<u8>> {
+        let delim = self.delim;
+        self.try_read_raw_to_byte(delim)
+            .ok()
+            .flatten()
+            .map(|(bytes, _)| bytes)
+            .or_else(|| self.empty_buffer_raw())
+            .map(|mut bytes| {
+                if delim == b'\n' && bytes.last() == Some(&b'\r') {
+                    bytes.pop();
+                }
+                bytes
+            })
+    }
+
+    /// Reads a line and parses it into `T`, splitting the line on ASCII whitespace.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new("3 foo 1.5".as_bytes());
+    /// let (a, b, c): (u32, String, f64) = input.parse_line();
+    /// assert_eq!(a, 3);
+    /// assert_eq!(b, "foo");
+    /// assert_eq!(c, 1.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - If there are no more lines to be read,
+    /// - If the line is not valid UTF-8,
+    /// - If the number of whitespace-separated fields does not match the arity of `T` or
+    /// - If any field fails to parse.
+    pub fn parse_line<T: FromInput>(&mut self) -> T {
+        self.line().parse_tuple()
+    }
+
+    /// Reads the next whitespace-separated token from the input, panicking if there isn't one.
+    ///
+    /// A token is a run of non-whitespace bytes; any ASCII whitespace between tokens (including
+    /// newlines) is skipped, so a token may straddle line boundaries.
+    ///
+    /// Note: the returned token's [Span] renders only the part of its line read so far, not the
+    /// whole line — unlike a `line()`-based read, the rest of the line may not have been buffered
+    /// yet when the token is read, so a caret diagnostic for anything but the last token on a
+    /// line will show a truncated source line.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new("3\n1 2\n3".as_bytes());
+    /// assert_eq!(input.next_token(), "3");
+    /// assert_eq!(input.next_token(), "1");
+    /// assert_eq!(input.next_token(), "2");
+    /// assert_eq!(input.next_token(), "3");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - If the token is not valid UTF-8 or
+    /// - If there are no more tokens to be read.
+    ///
+    /// See [try_next_token](BadInput::try_next_token) for an alternative which only panics on
+    /// invalid UTF-8
+    pub fn next_token(&mut self) -> InputString {
+        self.try_next_token().unwrap()
+    }
+
+    /// Creates an iterator over whitespace-separated tokens from the input. Note that only the
+    /// tokens pulled from the iterator are removed from the input. The iterator will panic if
+    /// invalid UTF-8 is encountered.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new("1 2 3".as_bytes());
+    /// let tokens: Vec<_> = input.tokens().collect();
+    /// assert_eq!(tokens, ["1", "2", "3"]);
+    /// ```
+    pub fn tokens<'a>(&'a mut self) -> impl Iterator<Item = InputString> + 'a {
+        Tokens { input: self }
+    }
+
+    /// Reads the next whitespace-separated token from the input, returning `None` if there are no
+    /// more tokens.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new("1 2".as_bytes());
+    /// assert_eq!(input.try_next_token(), Some("1".into()));
+    /// assert_eq!(input.try_next_token(), Some("2".into()));
+    /// assert_eq!(input.try_next_token(), None);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the token contains invalid UTF-8
+    pub fn try_next_token(&mut self) -> Option<InputString> {
+        self.try_read_token().and_then(|e| e.ok())
+    }
+
+    /// Reads the next whitespace-separated token and parses it as `F`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new("42 3.14".as_bytes());
+    /// assert_eq!(input.next::<u32>(), 42);
+    /// assert_eq!(input.next::<f64>(), 3.14);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - If there are no more tokens to be read or
+    /// - If the token could not be parsed to `F`, in which case the caret diagnostic may show a
+    ///   truncated source line — see the note on [next_token](BadInput::next_token).
+    // Named to read naturally as `.next::<F>()`; turbofish use keeps it unambiguous with
+    // `Iterator::next`, so the shadowed name is intentional.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<F: FromStr>(&mut self) -> F {
+        self.next_token().parse()
+    }
+
+    /// Creates an iterator that decodes the rest of the input as UTF-8 codepoints, one byte at a
+    /// time, without ever panicking on invalid UTF-8.
+    ///
+    /// Each well-formed sequence yields `Ok(char)`. An invalid leading byte, a truncated sequence
+    /// or an out-of-range codepoint yields `Err(byte)` for the leading byte and resumes decoding
+    /// from the very next byte, so a single bad byte does not desynchronize the rest of the
+    /// stream.
+    ///
+    /// # Examples
+    /// ```
+    /// use bad_input::BadInput;
+    ///
+    /// let mut input = BadInput::new(b"a\xFFb".as_slice());
+    /// let chars: Vec<_> = input.chars_lossy().collect();
+    /// assert_eq!(chars, [Ok('a'), Err(0xFF), Ok('b')]);
+    /// ```
+    pub fn chars_lossy<'a>(&'a mut self) -> impl Iterator<Item = Result<char, u8>> + 'a {
+        CharsLossy { input: self }
+    }
+
+    /// Reads the next UTF-8 codepoint from the input, lossily, returning `None` at the end of the
+    /// input.
+    fn next_char_lossy(&mut self) -> Option<Result<char, u8>> {
+        if !self.ensure_buffered(1) {
             return None;
         }
-        Some(String::from_utf8(std::mem::take(&mut self.buf)))
+        let b0 = self.buf[0];
+        let len = match b0 {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => {
+                self.consume_buffered(1);
+                return Some(Err(b0));
+            }
+        };
+        if !self.ensure_buffered(len) || !self.buf[1..len].iter().all(|b| b & 0xC0 == 0x80) {
+            self.consume_buffered(1);
+            return Some(Err(b0));
+        }
+        let codepoint = match len {
+            1 => b0 as u32,
+            2 => ((b0 as u32 & 0x1F) << 6) | (self.buf[1] as u32 & 0x3F),
+            3 => {
+                ((b0 as u32 & 0x0F) << 12)
+                    | ((self.buf[1] as u32 & 0x3F) << 6)
+                    | (self.buf[2] as u32 & 0x3F)
+            }
+            4 => {
+                ((b0 as u32 & 0x07) << 18)
+                    | ((self.buf[1] as u32 & 0x3F) << 12)
+                    | ((self.buf[2] as u32 & 0x3F) << 6)
+                    | (self.buf[3] as u32 & 0x3F)
+            }
+            _ => unreachable!(),
+        };
+        match char::from_u32(codepoint) {
+            Some(c) => {
+                self.consume_buffered(len);
+                Some(Ok(c))
+            }
+            None => {
+                self.consume_buffered(1);
+                Some(Err(b0))
+            }
+        }
+    }
+
+    /// Reads from the underlying reader until at least `n` bytes are buffered in `self.buf`,
+    /// without consuming any of them. Returns `false` if the input ends before `n` bytes are
+    /// available.
+    fn ensure_buffered(&mut self, n: usize) -> bool {
+        while self.buf.len() < n {
+            match self.reader.read(&mut self.read_buf) {
+                Ok(0) => return false,
+                Ok(bytes) => self.buf.extend_from_slice(&self.read_buf[..bytes]),
+                Err(e) => {
+                    if let std::io::ErrorKind::Interrupted = e.kind() {
+                        continue;
+                    }
+                    return false;
+                }
+            }
+        }
+        true
     }
 
-    fn try_read_to_byte(&mut self, p: u8) -> Option<Result<String, ReadToCharError>> {
-        let r = &mut self.reader;
+    /// Removes the first `n` bytes of `self.buf`, tracking each as consumed.
+    fn consume_buffered(&mut self, n: usize) {
+        let bytes: Vec<u8> = self.buf.drain(..n).collect();
+        for b in bytes {
+            self.track_consumed(b);
+        }
+    }
 
+    /// Records that `b` has just been consumed from the stream, advancing the running byte count
+    /// and the text of the line currently being read.
+    fn track_consumed(&mut self, b: u8) {
+        self.pos += 1;
+        if b == b'\n' {
+            self.line_no += 1;
+            self.line_start = self.pos;
+            self.line_buf.clear();
+        } else {
+            self.line_buf.push(b);
+        }
+    }
+
+    /// Builds the span for a piece of content `len` bytes long, starting at `offset`, on the line
+    /// currently being consumed.
+    fn make_span(&self, offset: usize, len: usize) -> Span {
+        Span::new(
+            offset,
+            len,
+            self.line_no,
+            self.line_start,
+            String::from_utf8_lossy(&self.line_buf).into_owned(),
+        )
+    }
+
+    fn try_read_token(&mut self) -> Option<Result<InputString, ReadToCharError>> {
         let mut old_buf = std::mem::take(&mut self.buf);
 
-        // First check if we already had the character in our buffer
+        let skip = old_buf
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(old_buf.len());
+        for b in old_buf[..skip].iter().copied() {
+            self.track_consumed(b);
+        }
+        old_buf.drain(..skip);
+
+        while old_buf.is_empty() {
+            match self.reader.read(&mut self.read_buf) {
+                Ok(0) => return None,
+                Ok(bytes) => {
+                    let skip = self.read_buf[..bytes]
+                        .iter()
+                        .position(|b| !b.is_ascii_whitespace())
+                        .unwrap_or(bytes);
+                    for i in 0..skip {
+                        let b = self.read_buf[i];
+                        self.track_consumed(b);
+                    }
+                    old_buf.extend_from_slice(&self.read_buf[skip..bytes]);
+                }
+                Err(e) => {
+                    if let std::io::ErrorKind::Interrupted = e.kind() {
+                        continue;
+                    }
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+
+        let content_start = self.pos;
+
         for (i, c) in old_buf.iter().copied().enumerate() {
-            if c == p {
-                self.buf.extend_from_slice(&old_buf[(i + 1)..]);
+            if c.is_ascii_whitespace() {
+                // The delimiter itself is left in `buf` for the next read, so it is tracked as
+                // consumed then rather than now.
+                let span = self.make_span(content_start, i);
+                self.buf.extend_from_slice(&old_buf[i..]);
                 old_buf.truncate(i);
                 return Some(
                     String::from_utf8(old_buf)
-                        .map(|s| s.into())
+                        .map(|s| InputString::with_span(s, span))
                         .map_err(|e| e.into()),
                 );
             }
+            self.track_consumed(c);
         }
 
-        // We didn't so we have to try to read some characters
         loop {
-            match r.read(&mut self.read_buf) {
+            match self.reader.read(&mut self.read_buf) {
                 Ok(bytes) => {
                     if bytes == 0 {
-                        self.buf = old_buf;
-                        return None;
+                        let span = self.make_span(content_start, old_buf.len());
+                        return Some(
+                            String::from_utf8(old_buf)
+                                .map(|s| InputString::with_span(s, span))
+                                .map_err(|e| e.into()),
+                        );
                     }
-                    let read = &self.read_buf[..bytes];
-                    for (i, b) in read.iter().copied().enumerate() {
-                        if b == p {
-                            self.buf.extend_from_slice(&read[(i + 1)..]);
+                    for i in 0..bytes {
+                        let b = self.read_buf[i];
+                        if b.is_ascii_whitespace() {
+                            // The delimiter itself is left in `buf` for the next read, so it is
+                            // tracked as consumed then rather than now.
+                            let span = self.make_span(content_start, old_buf.len());
+                            self.buf.extend_from_slice(&self.read_buf[i..bytes]);
                             return Some(
                                 String::from_utf8(old_buf)
-                                    .map(|s| s.into())
+                                    .map(|s| InputString::with_span(s, span))
                                     .map_err(|e| e.into()),
                             );
                         }
+                        self.track_consumed(b);
                         old_buf.push(b);
                     }
                 }
@@ -191,8 +543,108 @@ impl<R: Read> BadInput<R> {
             }
         }
     }
+
+    fn empty_buffer(&mut self) -> Option<Result<InputString, FromUtf8Error>> {
+        let span = self.make_span(self.pos - self.buf.len(), self.buf.len());
+        self.empty_buffer_raw()
+            .map(|bytes| String::from_utf8(bytes).map(|s| InputString::with_span(s, span)))
+    }
+
+    fn empty_buffer_raw(&mut self) -> Option<Vec<u8>> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.buf))
+    }
+
+    fn try_read_to_byte(&mut self, p: u8) -> Option<Result<InputString, ReadToCharError>> {
+        match self.try_read_raw_to_byte(p) {
+            Ok(Some((bytes, span))) => Some(
+                String::from_utf8(bytes)
+                    .map(|s| InputString::with_span(s, span))
+                    .map_err(|e| e.into()),
+            ),
+            Ok(None) => None,
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+
+    /// Reads raw bytes up to (but not including) the next occurrence of `p`, together with the
+    /// span of the content read, returning `Ok(None)` if the input ends before `p` is found. The
+    /// delimiter itself is consumed but not returned.
+    fn try_read_raw_to_byte(&mut self, p: u8) -> Result<Option<(Vec<u8>, Span)>, std::io::Error> {
+        let content_start = self.pos;
+        let mut old_buf = std::mem::take(&mut self.buf);
+
+        // First check if we already had the character in our buffer
+        for (i, c) in old_buf.iter().copied().enumerate() {
+            if c == p {
+                let span = self.make_span(content_start, i);
+                self.track_consumed(c);
+                self.buf.extend_from_slice(&old_buf[(i + 1)..]);
+                old_buf.truncate(i);
+                return Ok(Some((old_buf, span)));
+            }
+            self.track_consumed(c);
+        }
+
+        // We didn't so we have to try to read some characters
+        loop {
+            match self.reader.read(&mut self.read_buf) {
+                Ok(bytes) => {
+                    if bytes == 0 {
+                        self.buf = old_buf;
+                        return Ok(None);
+                    }
+                    for i in 0..bytes {
+                        let b = self.read_buf[i];
+                        if b == p {
+                            let span = self.make_span(content_start, old_buf.len());
+                            self.track_consumed(b);
+                            self.buf.extend_from_slice(&self.read_buf[(i + 1)..bytes]);
+                            return Ok(Some((old_buf, span)));
+                        }
+                        self.track_consumed(b);
+                        old_buf.push(b);
+                    }
+                }
+                Err(e) => {
+                    if let std::io::ErrorKind::Interrupted = e.kind() {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
 }
 
+impl<R: Read> BadInput<flate2::read::MultiGzDecoder<R>> {
+    /// Creates a new BadInput that transparently decompresses `reader` as gzip, using a streaming
+    /// multi-member decoder so concatenated `.gz` segments are read seamlessly as one stream.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::Write;
+    ///
+    /// use bad_input::BadInput;
+    /// use flate2::{write::GzEncoder, Compression};
+    ///
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// encoder.write_all(b"Hello, world!\n").unwrap();
+    /// let gz = encoder.finish().unwrap();
+    ///
+    /// let mut input = BadInput::gzip(gz.as_slice());
+    /// assert_eq!(input.line(), "Hello, world!");
+    /// ```
+    pub fn gzip(reader: R) -> Self {
+        Self::new(flate2::read::MultiGzDecoder::new(reader))
+    }
+}
+
+// Only the Ok/Err discriminant is used by callers (via `.ok()`); the wrapped errors are kept
+// purely so the `From` conversions (`.into()`) into this type have something to wrap.
+#[allow(dead_code)]
 enum ReadToCharError {
     InvalidUtf8(FromUtf8Error),
     IoError(std::io::Error),
@@ -210,7 +662,9 @@ impl From<FromUtf8Error> for ReadToCharError {
     }
 }
 
+mod from_input;
 mod input_string;
+mod span;
 
 struct Lines<'a, R: Read> {
     input: &'a mut BadInput<R>,
@@ -223,3 +677,27 @@ impl<'a, R: Read> Iterator for Lines<'a, R> {
         self.input.try_line()
     }
 }
+
+struct Tokens<'a, R: Read> {
+    input: &'a mut BadInput<R>,
+}
+
+impl<'a, R: Read> Iterator for Tokens<'a, R> {
+    type Item = InputString;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.input.try_next_token()
+    }
+}
+
+struct CharsLossy<'a, R: Read> {
+    input: &'a mut BadInput<R>,
+}
+
+impl<'a, R: Read> Iterator for CharsLossy<'a, R> {
+    type Item = Result<char, u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.input.next_char_lossy()
+    }
+}
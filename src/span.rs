@@ -0,0 +1,94 @@
+/// The location of an [InputString](crate::InputString) within the original input stream.
+///
+/// A `Span` is carried along as [BadInput](crate::BadInput) hands out lines and tokens, and is
+/// propagated by [InputString]'s splitting methods so that a failed parse can point back at the
+/// exact source location, even many splits removed from the original line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    offset: usize,
+    len: usize,
+    line: usize,
+    column: usize,
+    source_line: String,
+}
+
+impl Span {
+    pub(crate) fn new(
+        offset: usize,
+        len: usize,
+        line: usize,
+        line_start: usize,
+        source_line: String,
+    ) -> Self {
+        Self {
+            offset,
+            len,
+            line,
+            // With a custom delimiter a line can contain `\n`s, so it may start before
+            // `line_start`; its column is then clamped to 1 instead of underflowing.
+            column: offset.saturating_sub(line_start) + 1,
+            source_line,
+        }
+    }
+
+    /// Returns the span covering a sub-region of `self`, `local_offset` bytes into `self` and
+    /// `len` bytes long. Used by [InputString]'s splitting methods to propagate spans by
+    /// arithmetic on the parent's offset.
+    pub(crate) fn sub(&self, local_offset: usize, len: usize) -> Self {
+        Self {
+            offset: self.offset + local_offset,
+            len,
+            line: self.line,
+            column: self.column + local_offset,
+            source_line: self.source_line.clone(),
+        }
+    }
+
+    /// Shrinks the span by `n` bytes, dropping them off the end. Used when the `\r` of a line's
+    /// `\r\n` ending is stripped off.
+    pub(crate) fn truncate(&mut self, n: usize) {
+        self.len -= n;
+    }
+
+    /// The byte offset of the spanned region, relative to the start of the input stream.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The byte length of the spanned region.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the spanned region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The 1-based line number the spanned region starts on.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column the spanned region starts on.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Renders a multi-line caret diagnostic pointing at this span within its source line.
+    pub(crate) fn render(&self) -> String {
+        let underline_start = self.column - 1;
+        let underline_len = self
+            .len
+            .min(self.source_line.len().saturating_sub(underline_start))
+            .max(1);
+        format!(
+            "at line {}, column {}:\n{}\n{}{}",
+            self.line,
+            self.column,
+            self.source_line,
+            " ".repeat(underline_start),
+            "^".repeat(underline_len)
+        )
+    }
+}